@@ -1,6 +1,15 @@
 //! A hole that can only be filled once, and taken once.
 //!
 //! This has no dependency on libstd, and has only 32-bits of overhead per ivar.
+//!
+//! `IVarRd`/`IVarWr` are single-threaded only (see the `NoSend`/`NoSync`
+//! markers on the handles). For filling an ivar on one thread and reading it
+//! on another, use the `SyncIVarRd`/`SyncIVarWr` pair instead, which back
+//! themselves with an `AtomicU32` instead of a plain `u32`.
+//!
+//! `IVarRd::downgrade` hands out an `IVarWeak`, a non-owning handle that
+//! doesn't keep the contained value alive, for breaking reference cycles in
+//! graph-shaped computations.
 #![feature(phase)]
 #![feature(unsafe_destructor)]
 #![license = "MIT"]
@@ -15,6 +24,7 @@ extern crate core;
 #[cfg(test)] extern crate native;
 #[cfg(test)] extern crate test;
 
+use core::atomic::{AtomicU32, Ordering};
 use core::clone::Clone;
 use core::kinds::marker;
 use core::mem;
@@ -23,22 +33,41 @@ use alloc::owned::Box;
 use core::option::{Option,Some,None};
 use core::ptr;
 use core::ptr::RawPtr;
+use core::result::{Result,Ok,Err};
 
 /// The actual ivar cell that ends up on the heap. Reference counting and the
-/// "filled or not filled" bit are stored in the `meta`data field.
+/// "filled or not filled" state are stored in the `meta`data field.
 struct IVarCell<T> {
   data: T,
-  // 1 bit - was this ivar ever filled?
-  // 1 bit - is this ivar currently filled?
-  // 30 bits - How many strong refs to this cell are there?
+  // 1 bit   - was this ivar ever filled?
+  // 2 bits  - the data slot's state: EMPTY, FILLED, TAKEN (moved out by
+  //           `take`, slot holds garbage) or TAKEN_BORROWED (borrowed out by
+  //           `take_mut`, slot still holds a live `T`).
+  // 14 bits - how many strong (`IVarRd`) refs to this cell are there?
+  // 15 bits - how many weak (`IVarWeak`) refs to this cell are there?
   meta: u32,
 }
 
+const WAS_EVER_FILLED: u32 = 0x80000000;
+
+// The data slot's state lives in the 2 bits right below `WAS_EVER_FILLED`.
+// The fourth possible value, 0, is the implicit EMPTY state a fresh cell
+// starts in.
+const SLOT_STATE_MASK: u32    = 0x60000000;
+const SLOT_FILLED: u32         = 0x20000000;
+const SLOT_TAKEN: u32          = 0x40000000;
+const SLOT_TAKEN_BORROWED: u32 = 0x60000000;
+
+const STRONG_MASK: u32 = 0x1FFF8000;
+const STRONG_ONE: u32  = 0x00008000;
+const WEAK_MASK: u32   = 0x00007FFF;
+const WEAK_ONE: u32    = 0x00000001;
+
 impl<T> IVarCell<T> {
   fn new() -> IVarCell<T> {
     unsafe {
       IVarCell {
-        meta: 1u32, // start out with one strong ref.
+        meta: STRONG_ONE, // start out with one strong ref, no weak refs.
         data: mem::uninitialized(),
       }
     }
@@ -46,46 +75,98 @@ impl<T> IVarCell<T> {
 
   #[inline(always)]
   fn was_ever_filled(&self) -> bool {
-    (self.meta & 0x80000000u32) != 0
+    (self.meta & WAS_EVER_FILLED) != 0
+  }
+
+  #[inline(always)]
+  fn slot_state(&self) -> u32 {
+    self.meta & SLOT_STATE_MASK
+  }
+
+  /// Is there a live `T` sitting in the data slot right now, whether or not
+  /// it's currently reachable through `peek`/`take`/`take_mut`?
+  #[inline(always)]
+  fn has_live_value(&self) -> bool {
+    let state = self.slot_state();
+    state == SLOT_FILLED || state == SLOT_TAKEN_BORROWED
   }
 
   #[inline(always)]
   fn is_currently_filled(&self) -> bool {
-    (self.meta & 0x40000000u32) != 0
+    self.slot_state() == SLOT_FILLED
   }
 
+  /// Marks the slot taken by a value-moving `take`: the slot no longer holds
+  /// a live `T`, and can never become peekable/takeable again.
   #[inline(always)]
   fn mark_taken(&mut self) {
-    self.meta &= !0x40000000u32;
+    self.meta = (self.meta & !SLOT_STATE_MASK) | SLOT_TAKEN;
+  }
+
+  /// Marks the slot taken by a borrowing `take_mut`: the slot still holds a
+  /// live `T` (so it must still be dropped), but is not currently
+  /// peekable/takeable until a `heal`.
+  #[inline(always)]
+  fn mark_taken_borrowed(&mut self) {
+    self.meta = (self.meta & !SLOT_STATE_MASK) | SLOT_TAKEN_BORROWED;
   }
 
   #[inline(always)]
   fn set_filled(&mut self) {
-    self.meta |= 0xC0000000u32;
+    self.meta = (self.meta & !SLOT_STATE_MASK) | WAS_EVER_FILLED | SLOT_FILLED;
   }
 
   #[inline(always)]
   fn strong_refs(&self) -> u32 {
-    self.meta & !0x30000000u32
+    (self.meta & STRONG_MASK) >> 15
+  }
+
+  #[inline(always)]
+  fn weak_refs(&self) -> u32 {
+    self.meta & WEAK_MASK
   }
 
   #[inline(always)]
   fn inc_ref(&mut self) {
-    self.meta += 1;
+    self.meta += STRONG_ONE;
   }
 
-  /// Returns true iff the refcount is 0 after decrementing.
+  /// Returns true iff the strong refcount is 0 after decrementing.
   #[inline(always)]
   fn dec_ref(&mut self) -> bool {
-    self.meta -= 1;
+    self.meta -= STRONG_ONE;
     self.strong_refs() == 0
   }
 
+  #[inline(always)]
+  fn inc_weak(&mut self) {
+    self.meta += WEAK_ONE;
+  }
+
+  /// Returns true iff the weak refcount is 0 after decrementing.
+  #[inline(always)]
+  fn dec_weak(&mut self) -> bool {
+    self.meta -= WEAK_ONE;
+    self.weak_refs() == 0
+  }
+
   #[inline(always)]
   unsafe fn unsafe_read(&mut self) -> T {
     ptr::read(&self.data as *const T)
   }
 
+  /// Drops the contained value if the slot still holds a live one, without
+  /// freeing the cell itself. Called once the last strong ref goes away; the
+  /// cell allocation may still be kept alive by outstanding weak refs.
+  fn drop_data(&mut self) {
+    unsafe {
+      if self.has_live_value() {
+        self.mark_taken();
+        self.unsafe_read();
+      }
+    }
+  }
+
   fn take(&mut self) -> Option<T> {
     unsafe {
       if self.is_currently_filled() {
@@ -97,6 +178,33 @@ impl<T> IVarCell<T> {
     }
   }
 
+  /// Like `take`, but leaves the value in the cell instead of moving it out,
+  /// handing back a `&mut T` to it instead. The cell is marked
+  /// taken-borrowed, so no other `peek`/`take`/`take_mut` can alias this
+  /// reference, but the cell still owns (and will still drop) the value.
+  fn take_mut(&mut self) -> Option<&mut T> {
+    if self.is_currently_filled() {
+      self.mark_taken_borrowed();
+      Some(&mut self.data)
+    } else {
+      None
+    }
+  }
+
+  /// Un-marks a cell taken by `take_mut`, so the value already sitting in
+  /// the data slot becomes takeable again. Only succeeds when the slot is
+  /// taken-borrowed (never for a value-moving `take`, which leaves nothing
+  /// behind to heal) and the refcount is 1, i.e. there's no other handle
+  /// around that thinks the cell is still taken.
+  fn heal(&mut self) -> bool {
+    if self.slot_state() == SLOT_TAKEN_BORROWED && self.strong_refs() == 1 {
+      self.meta = (self.meta & !SLOT_STATE_MASK) | SLOT_FILLED;
+      true
+    } else {
+      false
+    }
+  }
+
   #[inline(always)]
   fn peek(&self) -> Option<&T> {
     if self.is_currently_filled() {
@@ -125,7 +233,7 @@ impl<T> IVarCell<T> {
 impl<T> Drop for IVarCell<T> {
   fn drop(&mut self) {
     unsafe {
-      if self.is_currently_filled() {
+      if self.has_live_value() {
         self.unsafe_read();
       }
     }
@@ -175,9 +283,74 @@ impl<T> Drop for IVar<T> {
 
     unsafe {
       if (*self.cell).dec_ref() {
+        (*self.cell).drop_data();
+
+        if (*self.cell).weak_refs() == 0 {
+          let _: Box<IVarCell<T>> = mem::transmute(self.cell);
+        }
+      }
+      self.cell = ptr::mut_null();
+    }
+  }
+}
+
+/// A non-owning handle to an IVar, following `Arc`/`Weak` semantics: it
+/// doesn't keep the contained `T` alive, only the bookkeeping `IVarCell`
+/// allocation behind it.
+///
+/// Get one with `IVarRd::downgrade`, and turn it back into a real `IVarRd`
+/// with `upgrade`, which fails once the last strong ref is gone.
+#[unsafe_no_drop_flag]
+pub struct IVarWeak<T> {
+  cell: *mut IVarCell<T>,
+  nosend: marker::NoSend,
+  nosync: marker::NoSync,
+}
+
+impl<T> IVarWeak<T> {
+  /// Attempts to promote this weak handle to a strong `IVarRd`. Returns
+  /// `None` if every strong handle has already been dropped.
+  pub fn upgrade(&self) -> Option<IVarRd<T>> {
+    unsafe {
+      if (*self.cell).strong_refs() == 0 {
+        return None;
+      }
+
+      (*self.cell).inc_ref();
+      Some(IVarRd {
+        inner: IVar {
+          cell:   self.cell,
+          nosend: marker::NoSend,
+          nosync: marker::NoSync,
+        },
+      })
+    }
+  }
+}
+
+impl<T> Clone for IVarWeak<T> {
+  fn clone(&self) -> IVarWeak<T> {
+    unsafe {
+      (*self.cell).inc_weak();
+      IVarWeak {
+        cell:   self.cell,
+        nosend: marker::NoSend,
+        nosync: marker::NoSync,
+      }
+    }
+  }
+}
+
+#[unsafe_destructor]
+impl<T> Drop for IVarWeak<T> {
+  fn drop(&mut self) {
+    if self.cell.is_null() { return; }
+
+    unsafe {
+      if (*self.cell).dec_weak() && (*self.cell).strong_refs() == 0 {
         let _: Box<IVarCell<T>> = mem::transmute(self.cell);
-        self.cell = ptr::mut_null();
       }
+      self.cell = ptr::mut_null();
     }
   }
 }
@@ -215,11 +388,41 @@ impl<T> IVarRd<T> {
 
   /// Was the IVar ever filled at any point in time? Note that if it is
   /// currently not filled, but it was filled at some point in the past, it will
-  /// never be filled again.
+  /// never be refilled with a new value (though `heal` can make the old value
+  /// takeable again).
   #[inline(always)]
   pub fn was_ever_filled(&self) -> bool {
     unsafe { (*self.inner.cell).was_ever_filled() }
   }
+
+  /// Like `take`, but borrows the value instead of moving it out, so the
+  /// cell keeps ownership of it. `None` if the value has either not been
+  /// filled, or already been taken/`take_mut`'d.
+  #[inline(always)]
+  pub fn take_mut(&mut self) -> Option<&mut T> {
+    unsafe { (*self.inner.cell).take_mut() }
+  }
+
+  /// Un-takes a value previously removed with `take_mut`, so it becomes
+  /// takeable again. Only succeeds if this is the only read handle left
+  /// pointing at the cell; returns whether healing happened.
+  #[inline(always)]
+  pub fn heal(&mut self) -> bool {
+    unsafe { (*self.inner.cell).heal() }
+  }
+
+  /// Creates a non-owning `IVarWeak` pointing at the same cell. Doesn't keep
+  /// the contained value alive, only the cell allocation itself.
+  pub fn downgrade(&self) -> IVarWeak<T> {
+    unsafe {
+      (*self.inner.cell).inc_weak();
+      IVarWeak {
+        cell:   self.inner.cell,
+        nosend: marker::NoSend,
+        nosync: marker::NoSync,
+      }
+    }
+  }
 }
 
 impl<T> Clone for IVarRd<T> {
@@ -262,10 +465,321 @@ pub fn new<T>() -> (IVarRd<T>, IVarWr<T>) {
   (IVarRd { inner: iv_rd }, IVarWr { inner: iv_wr })
 }
 
+/// A single-owner ivar that fills itself lazily, OnceCell-style.
+///
+/// There's no read/write handle split here: `get_or_init` both holds the
+/// storage and does the one-time initialization, so a single `LazyIVar` is
+/// all a caller needs.
+pub struct LazyIVar<T> {
+  data: T,
+  filled: core::cell::Cell<bool>,
+}
+
+impl<T> LazyIVar<T> {
+  /// Creates a new, empty `LazyIVar`.
+  pub fn new() -> LazyIVar<T> {
+    unsafe {
+      LazyIVar {
+        data: mem::uninitialized(),
+        filled: core::cell::Cell::new(false),
+      }
+    }
+  }
+
+  /// Returns the stored value, initializing it with `f` on the first call.
+  /// Later calls, on this or any other reference to the same `LazyIVar`,
+  /// just return the value `f` produced the first time.
+  pub fn get_or_init<F: FnOnce() -> T>(&self, f: F) -> &T {
+    if !self.filled.get() {
+      unsafe {
+        let data_ptr: *mut T = mem::transmute(&self.data);
+        ptr::write(data_ptr, f());
+      }
+      self.filled.set(true);
+    }
+
+    &self.data
+  }
+
+  /// Like `get_or_init`, but the initializer may fail. If `f` returns `Err`,
+  /// the cell is left unfilled so a later call can retry the initialization.
+  pub fn get_or_try_init<F, E>(&self, f: F) -> Result<&T, E>
+      where F: FnOnce() -> Result<T, E> {
+    if !self.filled.get() {
+      let t = try!(f());
+      unsafe {
+        let data_ptr: *mut T = mem::transmute(&self.data);
+        ptr::write(data_ptr, t);
+      }
+      self.filled.set(true);
+    }
+
+    Ok(&self.data)
+  }
+}
+
+#[unsafe_destructor]
+impl<T> Drop for LazyIVar<T> {
+  fn drop(&mut self) {
+    unsafe {
+      if self.filled.get() {
+        ptr::read(&self.data as *const T);
+      }
+    }
+  }
+}
+
+// ---------------------------------------------------------------------------
+// Thread-safe variant.
+//
+// Same shape as the `IVarCell` above, but `meta` is an `AtomicU32` so that
+// one thread may fill the cell while another peeks at it. The fill state is
+// a little state machine: INCOMPLETE -> WRITING -> COMPLETE. `fill` CASes
+// INCOMPLETE to WRITING to claim the right to write, does the `ptr::write`,
+// then publishes COMPLETE with `Release`. `peek`/`is_filled` load with
+// `Acquire` and only look at `data` once they observe COMPLETE, which is
+// what makes the write visible on the reading thread.
+
+const SYNC_STATE_MASK: u32 = 0x3;
+const SYNC_INCOMPLETE: u32 = 0;
+const SYNC_WRITING:    u32 = 1;
+const SYNC_COMPLETE:   u32 = 2;
+
+// The strong refcount lives above the two state bits.
+const SYNC_STRONG_ONE: u32 = 0x4;
+
+struct SyncIVarCell<T> {
+  data: T,
+  meta: AtomicU32,
+}
+
+impl<T> SyncIVarCell<T> {
+  fn new() -> SyncIVarCell<T> {
+    unsafe {
+      SyncIVarCell {
+        data: mem::uninitialized(),
+        meta: AtomicU32::new(SYNC_STRONG_ONE | SYNC_INCOMPLETE),
+      }
+    }
+  }
+
+  #[inline(always)]
+  fn state(&self) -> u32 {
+    self.meta.load(Ordering::Acquire) & SYNC_STATE_MASK
+  }
+
+  #[inline(always)]
+  fn is_filled(&self) -> bool {
+    self.state() == SYNC_COMPLETE
+  }
+
+  #[inline(always)]
+  fn peek(&self) -> Option<&T> {
+    if self.is_filled() {
+      Some(&self.data)
+    } else {
+      None
+    }
+  }
+
+  #[inline(always)]
+  unsafe fn unsafe_read(&self) -> T {
+    ptr::read(&self.data as *const T)
+  }
+
+  #[inline(always)]
+  unsafe fn unsafe_write(&self, t: T) {
+    let data_ptr: *mut T = mem::transmute(&self.data);
+    ptr::write(data_ptr, t)
+  }
+
+  /// Fills the cell. Only the first caller across all threads wins; every
+  /// other call is a silent no-op, and its `t` is dropped.
+  fn fill(&self, t: T) {
+    loop {
+      let cur = self.meta.load(Ordering::Relaxed);
+      if cur & SYNC_STATE_MASK != SYNC_INCOMPLETE {
+        return;
+      }
+      let claimed = (cur & !SYNC_STATE_MASK) | SYNC_WRITING;
+      if self.meta.compare_and_swap(cur, claimed, Ordering::Relaxed) == cur {
+        break;
+      }
+    }
+
+    unsafe { self.unsafe_write(t); }
+
+    loop {
+      let cur = self.meta.load(Ordering::Relaxed);
+      let done = (cur & !SYNC_STATE_MASK) | SYNC_COMPLETE;
+      if self.meta.compare_and_swap(cur, done, Ordering::Release) == cur {
+        break;
+      }
+    }
+  }
+
+  #[inline(always)]
+  fn strong_refs(&self) -> u32 {
+    self.meta.load(Ordering::Relaxed) >> 2
+  }
+
+  #[inline(always)]
+  fn inc_ref(&self) {
+    self.meta.fetch_add(SYNC_STRONG_ONE, Ordering::Relaxed);
+  }
+
+  /// Returns true iff the refcount is 0 after decrementing.
+  #[inline(always)]
+  fn dec_ref(&self) -> bool {
+    (self.meta.fetch_sub(SYNC_STRONG_ONE, Ordering::Release) >> 2) == 1
+  }
+}
+
+#[unsafe_destructor]
+impl<T> Drop for SyncIVarCell<T> {
+  fn drop(&mut self) {
+    unsafe {
+      if self.is_filled() {
+        self.unsafe_read();
+      }
+    }
+  }
+}
+
+/// A handle to a heap-allocated, thread-safe ivar cell.
+///
+/// Like `IVar`, this is not exported, since it allows both reading and
+/// writing.
+#[unsafe_no_drop_flag]
+struct SyncIVar<T> {
+  cell: *mut SyncIVarCell<T>,
+}
+
+unsafe impl<T: Send+Sync> Send for SyncIVar<T> {}
+unsafe impl<T: Send+Sync> Sync for SyncIVar<T> {}
+
+impl<T> SyncIVar<T> {
+  fn new() -> SyncIVar<T> {
+    unsafe {
+      let the_box: Box<SyncIVarCell<T>> = box SyncIVarCell::new();
+      let as_ptr: *mut SyncIVarCell<T> = mem::transmute(the_box);
+      SyncIVar { cell: as_ptr }
+    }
+  }
+
+  fn make_ref(&self) -> SyncIVar<T> {
+    unsafe {
+      (*self.cell).inc_ref();
+      SyncIVar { cell: self.cell }
+    }
+  }
+}
+
+#[unsafe_destructor]
+impl<T> Drop for SyncIVar<T> {
+  fn drop(&mut self) {
+    if self.cell.is_null() { return; }
+
+    unsafe {
+      if (*self.cell).dec_ref() {
+        let _: Box<SyncIVarCell<T>> = mem::transmute(self.cell);
+        self.cell = ptr::mut_null();
+      }
+    }
+  }
+}
+
+/// A reading handle to a `SyncIVar`, safe to send to, or share with, another
+/// thread.
+pub struct SyncIVarRd<T> {
+  inner: SyncIVar<T>,
+}
+
+impl<T> SyncIVarRd<T> {
+  /// Attempt to get a reference to the filled value. `None` is returned if
+  /// no other thread has finished filling it yet.
+  #[inline(always)]
+  pub fn peek(&self) -> Option<&T> {
+    unsafe { (*self.inner.cell).peek() }
+  }
+
+  /// Does the IVar currently have a payload ready?
+  #[inline(always)]
+  pub fn is_filled(&self) -> bool {
+    unsafe { (*self.inner.cell).is_filled() }
+  }
+
+  /// Spins until another thread fills the IVar, then returns a reference to
+  /// the value. Since this crate is `#![no_std]`, there's no mutex or condvar
+  /// to block on, so this is a plain busy-wait loop - only use it when you
+  /// expect the fill to happen soon.
+  pub fn wait(&self) -> &T {
+    loop {
+      match self.peek() {
+        Some(t) => return t,
+        None => core::hint::spin_loop(),
+      }
+    }
+  }
+
+  /// Like `wait`, but gives up after `spins` iterations of the busy-wait
+  /// loop instead of spinning forever, returning `None` on timeout.
+  pub fn wait_timeout(&self, spins: u32) -> Option<&T> {
+    let mut remaining = spins;
+    loop {
+      match self.peek() {
+        Some(t) => return Some(t),
+        None => {
+          if remaining == 0 {
+            return None;
+          }
+          remaining -= 1;
+          core::hint::spin_loop();
+        }
+      }
+    }
+  }
+}
+
+impl<T> Clone for SyncIVarRd<T> {
+  fn clone(&self) -> SyncIVarRd<T> {
+    unsafe {
+      (*self.inner.cell).inc_ref();
+      SyncIVarRd {
+        inner: SyncIVar { cell: self.inner.cell },
+      }
+    }
+  }
+}
+
+/// A write handle to a `SyncIVar`, safe to send to another thread.
+///
+/// Just like `IVarWr`, `fill` takes this by-move, so the type system rules
+/// out a double-fill even across threads.
+pub struct SyncIVarWr<T> {
+  inner: SyncIVar<T>,
+}
+
+impl<T> SyncIVarWr<T> {
+  /// Places the payload into the IVar, consuming the write handle.
+  #[inline(always)]
+  pub fn fill(self, t: T) {
+    unsafe { (*self.inner.cell).fill(t) }
+  }
+}
+
+/// Creates a new thread-safe IVar, with a reading and writing handle.
+pub fn sync_new<T>() -> (SyncIVarRd<T>, SyncIVarWr<T>) {
+  let iv_wr = SyncIVar::new();
+  let iv_rd = iv_wr.make_ref();
+  (SyncIVarRd { inner: iv_rd }, SyncIVarWr { inner: iv_wr })
+}
+
 #[cfg(test)]
 mod my_test {
-  use super::new;
+  use super::{new, sync_new};
   use std::option::{None,Some};
+  use std::result::{Result,Ok,Err};
 
   #[test]
   fn simple_usage() {
@@ -277,4 +791,125 @@ mod my_test {
     assert_eq!(rd.take(), Some(1u));
     assert_eq!(rd.peek(), None);
   }
+
+  #[test]
+  fn sync_simple_usage() {
+    let (rd, wr) = sync_new();
+
+    assert_eq!(rd.peek(), None);
+    wr.fill(1u);
+    assert_eq!(rd.peek(), Some(&1u));
+  }
+
+  #[test]
+  fn lazy_get_or_init_runs_once() {
+    use super::LazyIVar;
+    use std::cell::Cell;
+
+    let calls = Cell::new(0u);
+    let lazy = LazyIVar::new();
+
+    assert_eq!(*lazy.get_or_init(|| { calls.set(calls.get() + 1); 1u }), 1u);
+    assert_eq!(*lazy.get_or_init(|| { calls.set(calls.get() + 1); 2u }), 1u);
+    assert_eq!(calls.get(), 1u);
+  }
+
+  #[test]
+  fn sync_wait_timeout() {
+    let (rd, _wr) = sync_new::<uint>();
+
+    assert_eq!(rd.wait_timeout(16u32), None);
+  }
+
+  #[test]
+  fn sync_wait_returns_filled_value() {
+    let (rd, wr) = sync_new();
+
+    wr.fill(1u);
+    assert_eq!(rd.wait(), &1u);
+  }
+
+  #[test]
+  fn weak_upgrade_succeeds_while_strong_alive() {
+    let (rd, wr) = new();
+    wr.fill(1u);
+
+    let weak = rd.downgrade();
+    let upgraded = weak.upgrade();
+    assert_eq!(upgraded.unwrap().peek(), Some(&1u));
+  }
+
+  #[test]
+  fn weak_upgrade_fails_after_strong_dropped() {
+    let weak = {
+      let (rd, wr) = new();
+      wr.fill(1u);
+      rd.downgrade()
+    };
+
+    assert!(weak.upgrade().is_none());
+  }
+
+  #[test]
+  fn take_mut_and_heal() {
+    let (mut rd, wr) = new();
+
+    wr.fill(1u);
+    *rd.take_mut().unwrap() += 1u;
+    assert_eq!(rd.take_mut(), None);
+    assert!(rd.heal());
+    assert_eq!(rd.take(), Some(2u));
+  }
+
+  #[test]
+  fn heal_does_not_resurrect_a_moved_take() {
+    let (mut rd, wr) = new();
+
+    wr.fill(1u);
+    assert_eq!(rd.take(), Some(1u));
+    // `take` moved the value out for good; `heal` must not be fooled into
+    // thinking the still-filled-looking bits mean there's a value to give
+    // back.
+    assert!(!rd.heal());
+    assert_eq!(rd.take(), None);
+  }
+
+  #[test]
+  fn take_mut_without_heal_still_drops_on_cell_drop() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    struct DropCounter {
+      count: Rc<Cell<uint>>,
+    }
+
+    impl Drop for DropCounter {
+      fn drop(&mut self) {
+        self.count.set(self.count.get() + 1);
+      }
+    }
+
+    let count = Rc::new(Cell::new(0u));
+
+    {
+      let (mut rd, wr) = new();
+      wr.fill(DropCounter { count: count.clone() });
+      // Leaves the value in the slot, marked taken-borrowed, and never heals
+      // it back. The cell still owns the value and must drop it exactly
+      // once when `rd` goes away.
+      rd.take_mut();
+    }
+
+    assert_eq!(count.get(), 1u);
+  }
+
+  #[test]
+  fn lazy_get_or_try_init_retries_on_err() {
+    use super::LazyIVar;
+
+    let lazy: LazyIVar<u32> = LazyIVar::new();
+
+    assert!(lazy.get_or_try_init(|| -> Result<u32, ()> { Err(()) }).is_err());
+    assert_eq!(lazy.get_or_try_init(|| -> Result<u32, ()> { Ok(7u32) }), Ok(&7u32));
+  }
 }